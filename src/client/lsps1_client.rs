@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use cln_plugin::{Error, Plugin};
+use serde_json::{json, Value};
+
+use crate::{
+    client::validate_and_pay::Lsps1ValidateAndPay,
+    constants::{self, LSPS1_CREATE_ORDER_METHOD},
+    notifications,
+    pending::send_lsps_request,
+    PluginState,
+};
+
+/// Handler for the `buy-inbound-channel` RPC method: sends an LSPS1
+/// `create_order` request to `peer_id` over a custom message, waits for the
+/// matching response, then validates and pays for the order.
+pub async fn lsps1_client(plugin: Plugin<Arc<PluginState>>, v: Value) -> Result<Value, Error> {
+    let peer_id = v
+        .get("peer_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("buy-inbound-channel requires a peer_id"))?;
+    let lsp_balance_sat = v
+        .get("lsp_balance_sat")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("buy-inbound-channel requires a lsp_balance_sat"))?
+        .to_string();
+
+    let mut client = plugin.state().rpc.checkout(&plugin).await?;
+
+    let order: crate::constants::OrderResult = match send_lsps_request(
+        &plugin.state().pending,
+        &mut client,
+        peer_id,
+        LSPS1_CREATE_ORDER_METHOD,
+        json!({ "lsp_balance_sat": lsp_balance_sat }),
+    )
+    .await
+    {
+        Ok(order) => order,
+        Err(e) => {
+            plugin.state().rpc.checkin(client).await;
+            let rpc_error = constants::as_rpc_error(&e);
+            notifications::order_failed(&plugin, None, peer_id, &e.to_string(), rpc_error.as_ref())
+                .await?;
+            return Err(e);
+        }
+    };
+
+    let order_id = order.order_id.clone();
+    notifications::order_created(&plugin, &order_id, peer_id, &lsp_balance_sat).await?;
+
+    let mut validator = Lsps1ValidateAndPay {
+        requested_lsp_balance_sat: lsp_balance_sat,
+        client,
+        order,
+        plugin: plugin.clone(),
+        peer_id: peer_id.to_string(),
+    };
+    let result = validator.validate_and_pay().await;
+    plugin.state().rpc.checkin(validator.client).await;
+    result?;
+
+    Ok(json!({ "order_id": order_id }))
+}
@@ -0,0 +1,2 @@
+pub mod lsps1_client;
+pub mod validate_and_pay;
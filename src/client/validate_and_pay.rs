@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use cln_plugin::{Error, Plugin};
+use cln_rpc::{model::requests::PayRequest, ClnRpc};
+
+use crate::{
+    constants::{self, OrderResult},
+    notifications, PluginState,
+};
+
+/// Validates a provider's `create_order` response against what the client
+/// asked for, then pays the returned bolt11 invoice, emitting the
+/// `lsps1_order_*` notifications as the order moves through that process.
+pub struct Lsps1ValidateAndPay {
+    pub requested_lsp_balance_sat: String,
+    pub client: ClnRpc,
+    pub order: OrderResult,
+    pub plugin: Plugin<Arc<PluginState>>,
+    pub peer_id: String,
+}
+
+impl Lsps1ValidateAndPay {
+    pub async fn validate_and_pay(&mut self) -> Result<(), Error> {
+        if let Err(e) = self.try_validate_and_pay().await {
+            let rpc_error = constants::as_rpc_error(&e);
+            notifications::order_failed(
+                &self.plugin,
+                Some(self.order.order_id.as_str()),
+                &self.peer_id,
+                &e.to_string(),
+                rpc_error.as_ref(),
+            )
+            .await?;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    async fn try_validate_and_pay(&mut self) -> Result<(), Error> {
+        let order = &self.order;
+
+        if order.lsp_balance_sat != self.requested_lsp_balance_sat {
+            return Err(anyhow::anyhow!(
+                "order {} offered {} sat, expected {}",
+                order.order_id,
+                order.lsp_balance_sat,
+                self.requested_lsp_balance_sat
+            ));
+        }
+
+        let invoice = order.payment.bolt11.invoice.clone();
+        let order_total_sat = order.payment.bolt11.order_total_sat.clone();
+
+        self.client
+            .call_typed(&PayRequest {
+                bolt11: invoice,
+                amount_msat: None,
+                label: None,
+                riskfactor: None,
+                maxfeepercent: None,
+                retry_for: None,
+                maxdelay: None,
+                exemptfee: None,
+                exclude: None,
+                maxfee: None,
+                description: None,
+                partial_msat: None,
+            })
+            .await?;
+
+        log::info!("Paid order {}", order.order_id);
+        notifications::order_paid(&self.plugin, &order.order_id, &self.peer_id, &order_total_sat)
+            .await?;
+
+        // The LSP opens the channel once it sees this payment, but only
+        // asynchronously; `lsps1_channel_opened` is emitted on the provider
+        // side once the channel actually exists, not here.
+
+        Ok(())
+    }
+}
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// BOLT8 custom message type reserved for LSPS1 JSON-RPC framing.
+pub const MESSAGE_TYPE: u16 = 37913;
+
+pub const LSPS1_GET_INFO_METHOD: &str = "lsps1.get_info";
+pub const LSPS1_CREATE_ORDER_METHOD: &str = "lsps1.create_order";
+pub const LSPS1_GET_ORDER_METHOD: &str = "lsps1.get_order";
+
+pub const LSPS2_GET_INFO_METHOD: &str = "lsps2.get_info";
+pub const LSPS2_BUY_METHOD: &str = "lsps2.buy";
+
+/// Custom CLN notification topics this plugin emits as an LSPS1 order moves
+/// through its lifecycle: created, paid and failed for orders it buys,
+/// channel opened for orders it sells.
+pub const NOTIFICATION_ORDER_CREATED: &str = "lsps1_order_created";
+pub const NOTIFICATION_ORDER_PAID: &str = "lsps1_order_paid";
+pub const NOTIFICATION_CHANNEL_OPENED: &str = "lsps1_channel_opened";
+pub const NOTIFICATION_ORDER_FAILED: &str = "lsps1_order_failed";
+
+/// Minimal envelope used only to recover the correlation `id` before the
+/// full shape of a response is known.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonRpcEnvelope {
+    pub jsonrpc: String,
+    pub id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetInfoJsonRpcResponse {
+    pub jsonrpc: String,
+    pub id: String,
+    pub result: GetInfoResult,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetInfoResult {
+    pub options: GetInfoOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetInfoOptions {
+    pub min_channel_balance_sat: String,
+    pub max_channel_balance_sat: String,
+    pub min_initial_client_balance_sat: String,
+    pub max_initial_client_balance_sat: String,
+    pub min_initial_lsp_balance_sat: String,
+    pub max_initial_lsp_balance_sat: String,
+    pub max_channel_expiry_blocks: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateOrderJsonRpcResponse {
+    pub jsonrpc: String,
+    pub id: String,
+    pub result: OrderResult,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderResult {
+    pub order_id: String,
+    pub lsp_balance_sat: String,
+    pub client_balance_sat: String,
+    pub order_state: String,
+    pub payment: PaymentDetails,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentDetails {
+    pub bolt11: Bolt11PaymentDetails,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bolt11PaymentDetails {
+    pub invoice: String,
+    pub fee_total_sat: String,
+    pub order_total_sat: String,
+}
+
+/// A JSON-RPC 2.0 `error` member, as returned by an LSP that rejects a
+/// request instead of answering it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+/// LSPS1 application error codes, as defined by the spec's `error.code`
+/// values, layered on top of the raw [`RpcError`] they were decoded from.
+#[derive(Debug, Clone)]
+pub enum Lsps1Error {
+    /// code 1: the client's order parameters were rejected outright.
+    ClientRejectedOrder(RpcError),
+    /// code 100: the order the client is referencing no longer matches
+    /// what the LSP has on record for it.
+    OrderMismatch(RpcError),
+    /// code 1000: one of the requested options falls outside what the LSP
+    /// currently advertises.
+    OptionMismatch(RpcError),
+    /// Any other application error code the LSP returned.
+    Other(RpcError),
+}
+
+impl Lsps1Error {
+    pub fn from_rpc_error(err: RpcError) -> Self {
+        match err.code {
+            1 => Lsps1Error::ClientRejectedOrder(err),
+            100 => Lsps1Error::OrderMismatch(err),
+            1000 => Lsps1Error::OptionMismatch(err),
+            _ => Lsps1Error::Other(err),
+        }
+    }
+
+    pub fn rpc_error(&self) -> &RpcError {
+        match self {
+            Lsps1Error::ClientRejectedOrder(e)
+            | Lsps1Error::OrderMismatch(e)
+            | Lsps1Error::OptionMismatch(e)
+            | Lsps1Error::Other(e) => e,
+        }
+    }
+}
+
+impl std::fmt::Display for Lsps1Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let err = self.rpc_error();
+        write!(f, "LSPS1 error {}: {}", err.code, err.message)
+    }
+}
+
+impl std::error::Error for Lsps1Error {}
+
+/// One entry of an LSP's LSPS2 `opening_fee_params_menu`: the terms under
+/// which it is willing to open a JIT channel, with a `promise` the client
+/// hands back unmodified when it buys.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpeningFeeParams {
+    pub min_fee_msat: String,
+    pub proportional: u32,
+    pub valid_until: String,
+    pub min_lifetime: u32,
+    pub max_client_to_self_delay: u32,
+    pub promise: String,
+}
+
+impl OpeningFeeParams {
+    /// The fee the LSP is owed for opening a JIT channel that will forward
+    /// a first payment of `payment_size_msat`, per the LSPS2 fee formula:
+    /// the larger of the flat `min_fee_msat` and the `proportional` cut
+    /// (rounded up to the next msat).
+    pub fn opening_fee_msat(&self, payment_size_msat: u64) -> anyhow::Result<u64> {
+        let min_fee_msat: u64 = self.min_fee_msat.parse()?;
+        let proportional_fee_msat = payment_size_msat
+            .saturating_mul(self.proportional as u64)
+            .saturating_add(999_999)
+            / 1_000_000;
+
+        Ok(proportional_fee_msat.max(min_fee_msat))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Lsps2GetInfoResult {
+    pub opening_fee_params_menu: Vec<OpeningFeeParams>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lsps2BuyResult {
+    pub jit_channel_scid: String,
+    pub lsp_cltv_expiry_delta: u32,
+    pub client_trusts_lsp: bool,
+}
+
+/// Attempts to pull a JSON-RPC `error` member out of a decoded response and
+/// map it onto the LSPS1 error taxonomy. Returns `None` if `value` has no
+/// `error` member (i.e. it's a successful response).
+pub fn parse_error(value: &Value) -> Option<Lsps1Error> {
+    value
+        .get("error")
+        .and_then(|error| serde_json::from_value::<RpcError>(error.clone()).ok())
+        .map(Lsps1Error::from_rpc_error)
+}
+
+/// Recovers the [`RpcError`] behind `err`, if it was raised as a
+/// [`Lsps1Error`] (i.e. the peer answered with a JSON-RPC error rather than
+/// us failing for some local reason), so callers can thread it through to
+/// the `lsps1_order_failed` notification instead of only its message.
+pub fn as_rpc_error(err: &anyhow::Error) -> Option<RpcError> {
+    err.downcast_ref::<Lsps1Error>()
+        .map(|e| e.rpc_error().clone())
+}
@@ -0,0 +1,10 @@
+use crate::constants::OpeningFeeParams;
+
+/// A JIT channel this node (acting as the LSP) has promised to open once a
+/// payment matching `payment_size_msat` arrives over `jit_channel_scid`.
+#[derive(Debug, Clone)]
+pub struct JitChannel {
+    pub peer_id: String,
+    pub opening_fee_params: OpeningFeeParams,
+    pub payment_size_msat: u64,
+}
@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use cln_plugin::{Error, Plugin};
+use cln_rpc::ClnRpc;
+use serde_json::{json, Value};
+
+use crate::{
+    constants::{Lsps2BuyResult, Lsps2GetInfoResult, LSPS2_BUY_METHOD, LSPS2_GET_INFO_METHOD},
+    pending::{send_lsps_request, PendingRequests},
+    PluginState,
+};
+
+/// Handler for the `buy-jit-channel` RPC method: negotiates opening-fee
+/// params with `peer_id` and locks one in, so a channel opens the moment
+/// the first payment for it arrives instead of being paid for up front.
+pub async fn buy_jit_channel(plugin: Plugin<Arc<PluginState>>, v: Value) -> Result<Value, Error> {
+    let peer_id = v
+        .get("peer_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("buy-jit-channel requires a peer_id"))?;
+    let payment_size_msat = v
+        .get("payment_size_msat")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("buy-jit-channel requires a payment_size_msat"))?;
+
+    let mut client = plugin.state().rpc.checkout(&plugin).await?;
+    let result = negotiate(&plugin.state().pending, &mut client, peer_id, payment_size_msat).await;
+    plugin.state().rpc.checkin(client).await;
+
+    Ok(serde_json::to_value(result?)?)
+}
+
+/// Runs the two-step LSPS2 negotiation (`get_info` then `buy`) over an
+/// already-checked-out `client`, so the caller can check it back in
+/// regardless of which step fails.
+async fn negotiate(
+    pending: &PendingRequests,
+    client: &mut ClnRpc,
+    peer_id: &str,
+    payment_size_msat: u64,
+) -> Result<Lsps2BuyResult, Error> {
+    let get_info: Lsps2GetInfoResult =
+        send_lsps_request(pending, client, peer_id, LSPS2_GET_INFO_METHOD, json!({})).await?;
+
+    let chosen_params = get_info
+        .opening_fee_params_menu
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("peer {} advertised no opening_fee_params", peer_id))?;
+
+    send_lsps_request(
+        pending,
+        client,
+        peer_id,
+        LSPS2_BUY_METHOD,
+        json!({
+            "opening_fee_params": chosen_params,
+            "payment_size_msat": payment_size_msat,
+        }),
+    )
+    .await
+}
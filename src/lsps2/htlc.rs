@@ -0,0 +1,242 @@
+use std::{sync::Arc, time::Duration};
+
+use cln_plugin::{Error, Plugin};
+use cln_rpc::{
+    model::requests::{FundchannelRequest, ListpeerchannelsRequest},
+    primitives::{Amount, AmountOrAll},
+};
+use serde_json::{json, Value};
+
+use crate::{lsps2::channels::JitChannel, PluginState};
+
+/// BOLT #4 `temporary_channel_failure` failure code, returned when we can't
+/// forward a held JIT HTLC after all (underpaid, or the channel never came
+/// up in time).
+const TEMPORARY_CHANNEL_FAILURE: &str = "1007";
+
+/// How long we're willing to hold a JIT HTLC waiting for the channel we
+/// just funded to confirm and reach `CHANNELD_NORMAL`.
+const CHANNEL_READY_TIMEOUT: Duration = Duration::from_secs(60);
+const CHANNEL_READY_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Hook for `htlc_accepted`: if the incoming HTLC is routed over a scid we
+/// handed out as a JIT channel alias, opens the real channel sized to
+/// forward it, holds the HTLC until that specific channel is usable, then
+/// rewrites the forwarded onion to the channel's real scid and to the
+/// amount net of our opening fee.
+pub async fn htlc_accepted(plugin: Plugin<Arc<PluginState>>, v: Value) -> Result<Value, Error> {
+    let scid = match v
+        .get("onion")
+        .and_then(|onion| onion.get("short_channel_id"))
+        .and_then(|scid| scid.as_str())
+    {
+        Some(scid) => scid,
+        None => return Ok(json!({ "result": "continue" })),
+    };
+
+    let jit_channel = plugin.state().jit_channels.lock().await.get(scid).cloned();
+    let jit_channel = match jit_channel {
+        Some(jit_channel) => jit_channel,
+        None => return Ok(json!({ "result": "continue" })),
+    };
+
+    let amount_msat = v
+        .get("htlc")
+        .and_then(|htlc| htlc.get("amount_msat"))
+        .and_then(|amount| amount.as_u64())
+        .unwrap_or(0);
+
+    if amount_msat < jit_channel.payment_size_msat {
+        log::warn!(
+            "JIT channel over {} underpaid: got {} msat, expected {} msat",
+            scid,
+            amount_msat,
+            jit_channel.payment_size_msat
+        );
+        return Ok(json!({ "result": "fail", "failure_message": TEMPORARY_CHANNEL_FAILURE }));
+    }
+
+    let opening_fee_msat = jit_channel
+        .opening_fee_params
+        .opening_fee_msat(jit_channel.payment_size_msat)?;
+    let forward_amount_msat = amount_msat.saturating_sub(opening_fee_msat);
+
+    let channel_id = open_jit_channel(&plugin, &jit_channel, amount_msat).await?;
+    plugin.state().jit_channels.lock().await.remove(scid);
+
+    let real_scid = wait_until_channel_ready(&plugin, &jit_channel.peer_id, &channel_id).await?;
+    let real_scid = match real_scid {
+        Some(real_scid) => real_scid,
+        None => {
+            log::warn!(
+                "JIT channel {} to {} did not come up in time, failing the held HTLC",
+                channel_id,
+                jit_channel.peer_id
+            );
+            return Ok(json!({ "result": "fail", "failure_message": TEMPORARY_CHANNEL_FAILURE }));
+        }
+    };
+
+    let outgoing_cltv_value = v
+        .get("onion")
+        .and_then(|onion| onion.get("outgoing_cltv_value"))
+        .and_then(|cltv| cltv.as_u64())
+        .unwrap_or(0) as u32;
+
+    let payload = encode_forward_payload(forward_amount_msat, outgoing_cltv_value, &real_scid)?;
+
+    log::info!(
+        "Forwarding HTLC for JIT channel {} ({}) over real scid {}: {} msat minus {} msat opening fee",
+        channel_id,
+        scid,
+        real_scid,
+        amount_msat,
+        opening_fee_msat
+    );
+
+    Ok(json!({ "result": "continue", "payload": hex::encode(payload) }))
+}
+
+/// Opens the real channel the HTLC over `scid` is aliased to, sized to
+/// forward `amount_msat` on to the client. The LSP funds and keeps the
+/// whole channel balance (`push_msat: None`) so it has the liquidity to
+/// forward the HTLC once the channel is usable; the opening fee is
+/// recovered by forwarding less than we received, not by pushing funds at
+/// open time. Returns the new channel's `channel_id`, used to find its
+/// real scid once it confirms.
+async fn open_jit_channel(
+    plugin: &Plugin<Arc<PluginState>>,
+    jit_channel: &JitChannel,
+    amount_msat: u64,
+) -> Result<String, Error> {
+    let mut client = plugin.state().rpc.checkout(plugin).await?;
+
+    // Round up so the channel can carry the full HTLC amount.
+    let channel_amount_sat = (amount_msat + 999) / 1000;
+
+    let result = client
+        .call_typed(&FundchannelRequest {
+            id: jit_channel.peer_id.parse()?,
+            amount: AmountOrAll::Amount(Amount::from_sat(channel_amount_sat)),
+            push_msat: None,
+            announce: None,
+            feerate: None,
+            minconf: None,
+            close_to: None,
+            request_amt: None,
+            compact_lease: None,
+            utxos: None,
+            mindepth: None,
+            reserve: None,
+            channel_type: None,
+        })
+        .await;
+
+    plugin.state().rpc.checkin(client).await;
+    let response = result?;
+
+    log::info!(
+        "Opened JIT channel {} to {} with {} sat to forward a {} msat HTLC",
+        response.channel_id,
+        jit_channel.peer_id,
+        channel_amount_sat,
+        amount_msat
+    );
+
+    Ok(response.channel_id.to_string())
+}
+
+/// Polls `listpeerchannels` until the channel identified by `channel_id`
+/// reaches `CHANNELD_NORMAL`, returning its real scid, or gives up after
+/// [`CHANNEL_READY_TIMEOUT`]. Matching on `channel_id` (not just "any
+/// channel to this peer is normal") matters because the peer may already
+/// have an unrelated channel with us that's up well before the one we just
+/// funded.
+async fn wait_until_channel_ready(
+    plugin: &Plugin<Arc<PluginState>>,
+    peer_id: &str,
+    channel_id: &str,
+) -> Result<Option<String>, Error> {
+    let deadline = tokio::time::Instant::now() + CHANNEL_READY_TIMEOUT;
+
+    while tokio::time::Instant::now() < deadline {
+        let mut client = plugin.state().rpc.checkout(plugin).await?;
+        let response = client
+            .call_typed(&ListpeerchannelsRequest {
+                id: Some(peer_id.parse()?),
+            })
+            .await;
+        plugin.state().rpc.checkin(client).await;
+
+        let channels = serde_json::to_value(&response?)?;
+        let ready_scid = channels
+            .get("channels")
+            .and_then(|channels| channels.as_array())
+            .into_iter()
+            .flatten()
+            .find(|channel| {
+                channel.get("channel_id").and_then(|c| c.as_str()) == Some(channel_id)
+                    && channel.get("state").and_then(|s| s.as_str()) == Some("CHANNELD_NORMAL")
+            })
+            .and_then(|channel| channel.get("short_channel_id"))
+            .and_then(|scid| scid.as_str())
+            .map(str::to_string);
+
+        if ready_scid.is_some() {
+            return Ok(ready_scid);
+        }
+
+        tokio::time::sleep(CHANNEL_READY_POLL_INTERVAL).await;
+    }
+
+    Ok(None)
+}
+
+/// Builds a BOLT #4 per-hop TLV payload overriding `amt_to_forward` (type
+/// 2), `outgoing_cltv_value` (type 4) and `short_channel_id` (type 6), so
+/// the forward CLN makes after `continue` goes out over the JIT channel's
+/// real scid for the fee-adjusted amount instead of the original onion's.
+fn encode_forward_payload(
+    amt_to_forward_msat: u64,
+    outgoing_cltv_value: u32,
+    short_channel_id: &str,
+) -> Result<Vec<u8>, Error> {
+    let scid = parse_short_channel_id(short_channel_id)?;
+
+    let mut payload = Vec::new();
+    push_tlv(&mut payload, 2, trim_leading_zeros(&amt_to_forward_msat.to_be_bytes()));
+    push_tlv(&mut payload, 4, trim_leading_zeros(&outgoing_cltv_value.to_be_bytes()));
+    push_tlv(&mut payload, 6, &scid.to_be_bytes());
+
+    Ok(payload)
+}
+
+fn push_tlv(buf: &mut Vec<u8>, ty: u8, value: &[u8]) {
+    buf.push(ty);
+    buf.push(value.len() as u8);
+    buf.extend_from_slice(value);
+}
+
+/// Trims the leading all-zero bytes off a fixed-width big-endian integer,
+/// per BOLT #4's "truncated" TLV integer encoding (a zero value encodes as
+/// a zero-length field).
+fn trim_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let first_nonzero = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    &bytes[first_nonzero..]
+}
+
+/// Parses a `<block>x<tx>x<output>` short channel id into its packed u64.
+fn parse_short_channel_id(scid: &str) -> Result<u64, Error> {
+    let mut parts = scid.split('x');
+    let (Some(block), Some(tx), Some(output), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(anyhow::anyhow!("malformed short_channel_id {}", scid));
+    };
+
+    let block: u64 = block.parse()?;
+    let tx: u64 = tx.parse()?;
+    let output: u64 = output.parse()?;
+
+    Ok((block << 40) | (tx << 16) | output)
+}
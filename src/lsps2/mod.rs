@@ -0,0 +1,4 @@
+pub mod channels;
+pub mod client;
+pub mod htlc;
+pub mod server;
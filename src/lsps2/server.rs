@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use cln_plugin::{Error, Plugin};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    constants::{
+        Lsps2BuyResult, Lsps2GetInfoResult, OpeningFeeParams, LSPS2_BUY_METHOD,
+        LSPS2_GET_INFO_METHOD,
+    },
+    lsps2::channels::JitChannel,
+    PluginState,
+};
+
+/// Dispatches an inbound LSPS2 JSON-RPC request (we're acting as the LSP)
+/// to the matching handler and builds the JSON-RPC response to send back.
+pub async fn dispatch(
+    plugin: &Plugin<Arc<PluginState>>,
+    peer_id: &str,
+    method: &str,
+    id: &str,
+    params: &Value,
+) -> Value {
+    let result = match method {
+        LSPS2_GET_INFO_METHOD => get_info(plugin).await,
+        LSPS2_BUY_METHOD => buy(plugin, peer_id, params).await,
+        _ => Err(anyhow::anyhow!("unsupported LSPS2 method {}", method)),
+    };
+
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": 1, "message": e.to_string() },
+        }),
+    }
+}
+
+/// Advertises this node's opening-fee terms for a JIT channel, read from
+/// plugin configuration just like the LSPS1 `get_info` handler. The
+/// `promise` is a random UUID and is not a signature over the params;
+/// `buy` accepts the params the client sends back without checking it.
+async fn get_info(plugin: &Plugin<Arc<PluginState>>) -> Result<Value, Error> {
+    let min_fee_msat = plugin
+        .option("lsps2-min-fee-msat")
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_else(|| "2000000".to_string());
+    let proportional = plugin
+        .option("lsps2-proportional-ppm")
+        .and_then(|v| v.as_i64())
+        .unwrap_or(2000) as u32;
+
+    let params = OpeningFeeParams {
+        min_fee_msat,
+        proportional,
+        valid_until: "2286-11-20T17:46:40.000Z".to_string(),
+        min_lifetime: 144 * 30,
+        max_client_to_self_delay: 2016,
+        promise: Uuid::new_v4().to_string(),
+    };
+
+    let result = Lsps2GetInfoResult {
+        opening_fee_params_menu: vec![params],
+    };
+
+    Ok(serde_json::to_value(result)?)
+}
+
+/// Locks in the opening-fee params the client chose and reserves a JIT
+/// channel scid for it; [`crate::lsps2::htlc::htlc_accepted`] watches for
+/// an incoming HTLC over that scid to actually open the channel.
+async fn buy(plugin: &Plugin<Arc<PluginState>>, peer_id: &str, params: &Value) -> Result<Value, Error> {
+    let opening_fee_params: OpeningFeeParams =
+        serde_json::from_value(
+            params
+                .get("opening_fee_params")
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("buy requires opening_fee_params"))?,
+        )?;
+    let payment_size_msat = params
+        .get("payment_size_msat")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("buy requires payment_size_msat"))?;
+
+    let scid = generate_jit_scid();
+
+    plugin.state().jit_channels.lock().await.insert(
+        scid.clone(),
+        JitChannel {
+            peer_id: peer_id.to_string(),
+            opening_fee_params: opening_fee_params.clone(),
+            payment_size_msat,
+        },
+    );
+
+    let result = Lsps2BuyResult {
+        jit_channel_scid: scid,
+        lsp_cltv_expiry_delta: opening_fee_params.max_client_to_self_delay,
+        client_trusts_lsp: true,
+    };
+
+    Ok(serde_json::to_value(result)?)
+}
+
+/// Derives a short channel id alias to hand out for a JIT channel. This is
+/// only an alias until the real channel is opened, so it just needs to be
+/// unique, not tied to any actual block/transaction.
+fn generate_jit_scid() -> String {
+    let bytes = *Uuid::new_v4().as_bytes();
+    let block = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) % 800_000;
+    let tx = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) % 4_096;
+    format!("{block}x{tx}x0")
+}
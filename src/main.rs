@@ -1,12 +1,27 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{collections::HashMap, sync::Arc};
 
 mod client;
 mod constants;
+mod lsps2;
+mod notifications;
+mod pending;
+mod rpc;
+mod server;
 
 use client::lsps1_client::lsps1_client;
-use cln_plugin::{Builder, Error, Plugin};
-use cln_rpc::ClnRpc;
-use constants::{CreateOrderJsonRpcResponse, GetInfoJsonRpcResponse, MESSAGE_TYPE};
+use cln_plugin::{
+    options::{ConfigOption, Value as OptionValue},
+    Builder, Error, Plugin,
+};
+use cln_rpc::model::requests::SendcustommsgRequest;
+use constants::{JsonRpcEnvelope, MESSAGE_TYPE};
+use lsps2::{channels::JitChannel, client::buy_jit_channel, htlc::htlc_accepted};
+use pending::PendingRequests;
+use rpc::SharedRpc;
+use server::{
+    handlers::{list_inbound_orders, resume_pending_orders},
+    store::OrderStore,
+};
 
 use serde_json::json;
 use tokio::{
@@ -14,21 +29,25 @@ use tokio::{
     sync::Mutex,
 };
 
-use crate::{
-    client::validate_and_pay::Lsps1ValidateAndPay,
-    constants::{LSPS1_CREATE_ORDER_METHOD, LSPS1_GET_ORDER_METHOD},
-};
+const OPT_MIN_CHANNEL_BALANCE_SAT: &str = "lsps1-min-channel-balance-sat";
+const OPT_MAX_CHANNEL_BALANCE_SAT: &str = "lsps1-max-channel-balance-sat";
+const OPT_LSPS2_MIN_FEE_MSAT: &str = "lsps2-min-fee-msat";
+const OPT_LSPS2_PROPORTIONAL_PPM: &str = "lsps2-proportional-ppm";
 
-struct PluginState {
-    data: Mutex<HashMap<String, String>>,
-    method: Mutex<HashMap<String, String>>,
+pub struct PluginState {
+    pending: PendingRequests,
+    orders: OrderStore,
+    jit_channels: Mutex<HashMap<String, JitChannel>>,
+    rpc: SharedRpc,
 }
 
 impl PluginState {
     async fn new() -> Result<Self, Error> {
         Ok(Self {
-            data: Mutex::new(HashMap::new()),
-            method: Mutex::new(HashMap::new()),
+            pending: PendingRequests::new(),
+            orders: OrderStore::new(),
+            jit_channels: Mutex::new(HashMap::new()),
+            rpc: SharedRpc::new(),
         })
     }
 }
@@ -37,17 +56,57 @@ impl PluginState {
 async fn main() -> Result<(), Error> {
     let plugin_state = Arc::new(PluginState::new().await?);
 
-    if let Some(plugin) = Builder::new(stdin(), stdout())
+    let mut builder = Builder::new(stdin(), stdout())
         .dynamic()
+        .option(ConfigOption::new(
+            OPT_MIN_CHANNEL_BALANCE_SAT,
+            OptionValue::String("20000".to_string()),
+            "Minimum channel size (sat) this node will sell via LSPS1",
+        ))
+        .option(ConfigOption::new(
+            OPT_MAX_CHANNEL_BALANCE_SAT,
+            OptionValue::String("100000000".to_string()),
+            "Maximum channel size (sat) this node will sell via LSPS1",
+        ))
+        .option(ConfigOption::new(
+            OPT_LSPS2_MIN_FEE_MSAT,
+            OptionValue::String("2000000".to_string()),
+            "Minimum fee (msat) this node charges for an LSPS2 JIT channel",
+        ))
+        .option(ConfigOption::new(
+            OPT_LSPS2_PROPORTIONAL_PPM,
+            OptionValue::Integer(2000),
+            "Proportional fee (ppm of the first payment) for an LSPS2 JIT channel",
+        ))
         .rpcmethod(
             "buy-inbound-channel",
             "Buy an inbound channel from other peers",
             lsps1_client,
         )
+        .rpcmethod(
+            "buy-jit-channel",
+            "Buy a just-in-time inbound channel opened on first payment",
+            buy_jit_channel,
+        )
+        .rpcmethod(
+            "list-inbound-orders",
+            "List LSPS1 orders this node has sold, and their status",
+            list_inbound_orders,
+        )
         .hook("custommsg", subscribe_to_custom_message)
-        .start(plugin_state)
-        .await?
-    {
+        .hook("htlc_accepted", htlc_accepted);
+
+    for topic in notifications::TOPICS {
+        builder = builder.notification(*topic);
+    }
+
+    if let Some(plugin) = builder.start(plugin_state).await? {
+        let mut client = plugin.state().rpc.checkout(&plugin).await?;
+        plugin.state().orders.load(&mut client).await?;
+        plugin.state().rpc.checkin(client).await;
+
+        resume_pending_orders(&plugin).await;
+
         let plug_res = plugin.join().await;
 
         plug_res
@@ -60,11 +119,7 @@ async fn subscribe_to_custom_message(
     p: Plugin<Arc<PluginState>>,
     v: serde_json::Value,
 ) -> Result<serde_json::Value, Error> {
-    let state_ref = p.state().clone();
-
-    // Now, you can lock the mutex asynchronously
-    let data = state_ref.data.lock().await;
-    let method = state_ref.method.lock().await;
+    let state = p.state().clone();
 
     // Attempt to extract "payload"
     let payload_hex = match v.get("payload").and_then(|v| v.as_str()) {
@@ -94,70 +149,75 @@ async fn subscribe_to_custom_message(
         return Ok(json!({ "result": "continue" }));
     }
 
-    let conf = p.configuration();
-    let socket_path = Path::new(&conf.lightning_dir).join(&conf.rpc_file);
-    let client = ClnRpc::new(socket_path).await?;
-
     // Extract the JSON payload starting from the 3rd byte
     let json_bytes = &bytes[2..];
 
-    // Get info method response
-    match serde_json::from_slice::<GetInfoJsonRpcResponse>(json_bytes) {
-        Ok(json_payload) => {
-            log::info!("GetInfo Decoded JSON payload: {:?}", json_payload)
-        }
+    let decoded = match serde_json::from_slice::<serde_json::Value>(json_bytes) {
+        Ok(decoded) => decoded,
         Err(e) => {
-            log::warn!("GetInfo Failed to decode JSON payload: {}", e)
-        }
-    };
-
-    // Get order response method
-    // Get order and create order have the same response from server
-    match serde_json::from_slice::<CreateOrderJsonRpcResponse>(json_bytes) {
-        Ok(json_payload) => {
-            if method.get(&"method".to_string()) == Some(&LSPS1_GET_ORDER_METHOD.to_string()) {
-                log::info!("GetOrder Decoded JSON payload: {:?}", json_payload);
-            }
-        }
-        Err(e) => {
-            log::warn!("CreateOrder Failed to decode JSON payload: {}", e);
+            log::warn!("Failed to decode LSPS JSON-RPC payload: {}", e);
+            return Ok(json!({ "result": "continue" }));
         }
     };
 
-    // Create order response method
-    match serde_json::from_slice::<CreateOrderJsonRpcResponse>(json_bytes) {
-        Ok(json_payload) => {
-            if method.get(&"method".to_string()) != Some(&LSPS1_CREATE_ORDER_METHOD.to_string()) {
+    // An inbound `method` member means a peer is calling us as the LSP;
+    // anything else is a response to a request we (as the client) made.
+    if let Some(method) = decoded.get("method").and_then(|v| v.as_str()) {
+        let id = decoded.get("id").and_then(|v| v.as_str()).unwrap_or("");
+        let params = decoded.get("params").cloned().unwrap_or(json!({}));
+        let peer_id = match v.get("peer_id").and_then(|v| v.as_str()) {
+            Some(peer_id) => peer_id,
+            None => {
+                log::warn!("custommsg hook call had no peer_id");
                 return Ok(json!({ "result": "continue" }));
             }
+        };
 
-            log::info!("CreateOrder Decoded JSON payload: {:?}", json_payload);
-
-            let get_order = data.get(&json_payload.id);
-
-            if let Some(order) = get_order {
-                let res = Lsps1ValidateAndPay {
-                    order: order.to_string(),
-                    client,
-                    order_response_payload: json_payload,
-                }
-                .validate_and_pay()
-                .await;
-
-                match res {
-                    Ok(_) => {
-                        log::info!("Order validated and paid");
-                    }
-                    Err(e) => {
-                        log::error!("Order validation and payment failed: {}", e);
-                    }
-                }
-            }
-        }
+        let response = if method.starts_with("lsps2.") {
+            lsps2::server::dispatch(&p, peer_id, method, id, &params).await
+        } else {
+            server::handlers::dispatch(&p, peer_id, method, id, &params).await
+        };
+        send_custom_message(&p, peer_id, &response).await?;
+
+        return Ok(json!({ "result": "continue" }));
+    }
+
+    let envelope: JsonRpcEnvelope = match serde_json::from_value(decoded.clone()) {
+        Ok(envelope) => envelope,
         Err(e) => {
-            log::warn!("CreateOrder Failed to decode JSON payload: {}", e);
+            log::warn!("Failed to decode LSPS JSON-RPC envelope: {}", e);
+            return Ok(json!({ "result": "continue" }));
         }
     };
 
-    return Ok(json!({ "result": "continue" }));
+    // Route the response back to whichever outgoing LSPS request is waiting on
+    // this id; if nothing is waiting the peer replied to a request we never
+    // made (or already timed out on).
+    if !state.pending.complete(&envelope.id, decoded).await {
+        log::warn!("Received a response for unknown request id {}", envelope.id);
+    }
+
+    Ok(json!({ "result": "continue" }))
+}
+
+async fn send_custom_message(
+    p: &Plugin<Arc<PluginState>>,
+    peer_id: &str,
+    response: &serde_json::Value,
+) -> Result<(), Error> {
+    let mut payload = MESSAGE_TYPE.to_be_bytes().to_vec();
+    payload.extend_from_slice(&serde_json::to_vec(response)?);
+
+    let mut client = p.state().rpc.checkout(p).await?;
+    let result = client
+        .call_typed(&SendcustommsgRequest {
+            node_id: peer_id.parse()?,
+            msg: hex::encode(&payload),
+        })
+        .await;
+    p.state().rpc.checkin(client).await;
+
+    result?;
+    Ok(())
 }
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use cln_plugin::{Error, Plugin};
+use serde_json::json;
+
+use crate::{
+    constants::{
+        RpcError, NOTIFICATION_CHANNEL_OPENED, NOTIFICATION_ORDER_CREATED,
+        NOTIFICATION_ORDER_FAILED, NOTIFICATION_ORDER_PAID,
+    },
+    PluginState,
+};
+
+/// The custom notification topics this plugin registers with CLN so other
+/// plugins or a GUI can subscribe and react to channel-purchase events
+/// instead of polling `list-inbound-orders`.
+pub const TOPICS: &[&str] = &[
+    NOTIFICATION_ORDER_CREATED,
+    NOTIFICATION_ORDER_PAID,
+    NOTIFICATION_CHANNEL_OPENED,
+    NOTIFICATION_ORDER_FAILED,
+];
+
+pub async fn order_created(
+    plugin: &Plugin<Arc<PluginState>>,
+    order_id: &str,
+    peer_id: &str,
+    lsp_balance_sat: &str,
+) -> Result<(), Error> {
+    emit(
+        plugin,
+        NOTIFICATION_ORDER_CREATED,
+        json!({
+            "order_id": order_id,
+            "peer_id": peer_id,
+            "lsp_balance_sat": lsp_balance_sat,
+        }),
+    )
+    .await
+}
+
+pub async fn order_paid(
+    plugin: &Plugin<Arc<PluginState>>,
+    order_id: &str,
+    peer_id: &str,
+    amount_sat: &str,
+) -> Result<(), Error> {
+    emit(
+        plugin,
+        NOTIFICATION_ORDER_PAID,
+        json!({
+            "order_id": order_id,
+            "peer_id": peer_id,
+            "amount_sat": amount_sat,
+        }),
+    )
+    .await
+}
+
+pub async fn channel_opened(
+    plugin: &Plugin<Arc<PluginState>>,
+    order_id: &str,
+    peer_id: &str,
+) -> Result<(), Error> {
+    emit(
+        plugin,
+        NOTIFICATION_CHANNEL_OPENED,
+        json!({
+            "order_id": order_id,
+            "peer_id": peer_id,
+        }),
+    )
+    .await
+}
+
+pub async fn order_failed(
+    plugin: &Plugin<Arc<PluginState>>,
+    order_id: Option<&str>,
+    peer_id: &str,
+    message: &str,
+    rpc_error: Option<&RpcError>,
+) -> Result<(), Error> {
+    emit(
+        plugin,
+        NOTIFICATION_ORDER_FAILED,
+        json!({
+            "order_id": order_id,
+            "peer_id": peer_id,
+            "message": message,
+            "error": rpc_error,
+        }),
+    )
+    .await
+}
+
+async fn emit(
+    plugin: &Plugin<Arc<PluginState>>,
+    topic: &str,
+    payload: serde_json::Value,
+) -> Result<(), Error> {
+    plugin
+        .send_custom_notification(topic.to_string(), payload)
+        .await
+}
@@ -0,0 +1,115 @@
+use std::{collections::HashMap, str::FromStr, time::Duration};
+
+use anyhow::anyhow;
+use cln_plugin::Error;
+use cln_rpc::{model::requests::SendcustommsgRequest, primitives::PublicKey, ClnRpc};
+use serde_json::{json, Value};
+use tokio::sync::{oneshot, Mutex};
+use uuid::Uuid;
+
+use crate::constants::{self, MESSAGE_TYPE};
+
+/// How long we're willing to wait for a peer to answer an outgoing LSPS
+/// JSON-RPC request before giving up on it.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Correlates outgoing LSPS JSON-RPC requests with their eventual response
+/// by the request's `id`, so that concurrent `buy-inbound-channel` calls no
+/// longer clobber each other the way a single `method`/`data` map did.
+pub struct PendingRequests {
+    waiters: Mutex<HashMap<String, oneshot::Sender<Value>>>,
+}
+
+impl PendingRequests {
+    pub fn new() -> Self {
+        Self {
+            waiters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Registers `id` as awaiting a response, returning the receiver half.
+    /// Must be called before the corresponding request is sent, so that a
+    /// fast reply can never race ahead of the registration.
+    pub async fn register(&self, id: String) -> oneshot::Receiver<Value> {
+        let (tx, rx) = oneshot::channel();
+        self.waiters.lock().await.insert(id, tx);
+        rx
+    }
+
+    /// Waits for `rx` to resolve, falling back to [`DEFAULT_TIMEOUT`].
+    pub async fn wait(&self, id: &str, rx: oneshot::Receiver<Value>) -> Result<Value, Error> {
+        self.wait_timeout(id, rx, DEFAULT_TIMEOUT).await
+    }
+
+    /// Waits for `rx` to resolve, erroring out after `timeout` if the peer
+    /// never replies.
+    pub async fn wait_timeout(
+        &self,
+        id: &str,
+        rx: oneshot::Receiver<Value>,
+        timeout: Duration,
+    ) -> Result<Value, Error> {
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(_)) => Err(anyhow!(
+                "pending request {id} was dropped before it was answered"
+            )),
+            Err(_) => {
+                self.waiters.lock().await.remove(id);
+                Err(anyhow!("timed out waiting for a reply to request {id}"))
+            }
+        }
+    }
+
+    /// Resolves the waiter registered for `id`, if any. Returns `true` if a
+    /// waiter was found and notified.
+    pub async fn complete(&self, id: &str, value: Value) -> bool {
+        match self.waiters.lock().await.remove(id) {
+            Some(tx) => {
+                let _ = tx.send(value);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Builds an LSPS JSON-RPC request, sends it as a framed custom message to
+/// `peer_id` over `client`, and waits for the matching response. This is
+/// the correlation and error-handling plumbing shared by every LSPS* call
+/// this plugin makes as a client, whichever spec it belongs to.
+pub async fn send_lsps_request<T: serde::de::DeserializeOwned>(
+    pending: &PendingRequests,
+    client: &mut ClnRpc,
+    peer_id: &str,
+    method: &str,
+    params: Value,
+) -> Result<T, Error> {
+    let id = Uuid::new_v4().to_string();
+    let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+
+    let mut payload = MESSAGE_TYPE.to_be_bytes().to_vec();
+    payload.extend_from_slice(&serde_json::to_vec(&request)?);
+
+    let rx = pending.register(id.clone()).await;
+
+    client
+        .call_typed(&SendcustommsgRequest {
+            node_id: PublicKey::from_str(peer_id)?,
+            msg: hex::encode(&payload),
+        })
+        .await?;
+
+    let response = pending.wait(&id, rx).await?;
+
+    if let Some(err) = constants::parse_error(&response) {
+        return Err(err.into());
+    }
+
+    let result = response
+        .get("result")
+        .cloned()
+        .ok_or_else(|| anyhow!("LSPS response for {method} had neither a result nor an error"))?;
+
+    Ok(serde_json::from_value(result)?)
+}
@@ -0,0 +1,44 @@
+use std::{path::Path, sync::Arc};
+
+use cln_plugin::{Error, Plugin};
+use cln_rpc::ClnRpc;
+use tokio::sync::Mutex;
+
+use crate::PluginState;
+
+/// Holds at most one idle `ClnRpc` socket, reused across handlers instead of
+/// every custommsg, notification, or HTLC opening its own connection to
+/// lightningd. A checkout while that socket is already out opens another.
+///
+/// Connections are borrowed via [`Self::checkout`]/[`Self::checkin`]
+/// rather than held behind a lock guard, since callers typically want to
+/// keep using the connection across other `.await` points (e.g. while
+/// also awaiting a `PendingRequests` reply) that a guard can't survive.
+pub struct SharedRpc {
+    client: Mutex<Option<ClnRpc>>,
+}
+
+impl SharedRpc {
+    pub fn new() -> Self {
+        Self {
+            client: Mutex::new(None),
+        }
+    }
+
+    /// Hands out a connection, reusing the last checked-in one if there is
+    /// one, or opening a fresh socket otherwise.
+    pub async fn checkout(&self, plugin: &Plugin<Arc<PluginState>>) -> Result<ClnRpc, Error> {
+        if let Some(client) = self.client.lock().await.take() {
+            return Ok(client);
+        }
+
+        let conf = plugin.configuration();
+        let socket_path = Path::new(&conf.lightning_dir).join(&conf.rpc_file);
+        ClnRpc::new(socket_path).await
+    }
+
+    /// Returns a connection so the next [`Self::checkout`] can reuse it.
+    pub async fn checkin(&self, client: ClnRpc) {
+        *self.client.lock().await = Some(client);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod handlers;
+pub mod orders;
+pub mod store;
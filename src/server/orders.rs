@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+use crate::constants::OrderResult;
+
+/// An order as tracked by the provider side: the protocol-level
+/// [`OrderResult`] plus the bits the provider needs but never hands back to
+/// the client, such as who to open the channel to. Serializable so it can
+/// be round-tripped through CLN's datastore.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredOrder {
+    pub peer_id: String,
+    pub result: OrderResult,
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use cln_plugin::Error;
+use cln_rpc::{
+    model::requests::{DatastoreMode, DatastoreRequest, ListdatastoreRequest},
+    ClnRpc,
+};
+use tokio::sync::Mutex;
+
+use crate::server::orders::StoredOrder;
+
+/// Datastore path orders are filed under: `lsps1-orders/<order_id>`.
+const DATASTORE_PREFIX: &str = "lsps1-orders";
+
+/// Keeps LSPS1 orders in CLN's own `datastore`, so an order that was paid
+/// before a crash can still be reconciled after the plugin (or the whole
+/// node) restarts, instead of being lost along with an in-memory map.
+///
+/// An in-memory cache backs reads so handlers don't round-trip to the
+/// node's RPC on every lookup; every write goes through `datastore` first.
+pub struct OrderStore {
+    cache: Mutex<HashMap<String, StoredOrder>>,
+}
+
+impl OrderStore {
+    pub fn new() -> Self {
+        Self {
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Loads every order CLN's datastore has on file into the cache. Call
+    /// this once at startup, after the plugin has a working RPC socket.
+    pub async fn load(&self, client: &mut ClnRpc) -> Result<(), Error> {
+        let response = client
+            .call_typed(&ListdatastoreRequest {
+                key: Some(vec![DATASTORE_PREFIX.to_string()]),
+            })
+            .await?;
+
+        let mut cache = self.cache.lock().await;
+        for entry in response.datastore {
+            let Some(value) = entry.string else {
+                continue;
+            };
+
+            match serde_json::from_str::<StoredOrder>(&value) {
+                Ok(order) => {
+                    cache.insert(order.result.order_id.clone(), order);
+                }
+                Err(e) => {
+                    log::warn!("Ignoring unreadable datastore entry {:?}: {}", entry.key, e);
+                }
+            }
+        }
+
+        log::info!("Loaded {} LSPS1 order(s) from the datastore", cache.len());
+
+        Ok(())
+    }
+
+    /// Persists `order` to the datastore and updates the cache.
+    pub async fn insert(&self, client: &mut ClnRpc, order: StoredOrder) -> Result<(), Error> {
+        self.persist(client, &order).await?;
+        self.cache
+            .lock()
+            .await
+            .insert(order.result.order_id.clone(), order);
+        Ok(())
+    }
+
+    /// Returns a copy of the order for `order_id`, if we have one.
+    pub async fn get(&self, order_id: &str) -> Option<StoredOrder> {
+        self.cache.lock().await.get(order_id).cloned()
+    }
+
+    /// Returns a copy of every known order.
+    pub async fn all(&self) -> Vec<StoredOrder> {
+        self.cache.lock().await.values().cloned().collect()
+    }
+
+    /// Updates `order_id`'s `order_state` both in the datastore and cache.
+    /// Persists first so a failed write can't leave the cache claiming a
+    /// state the datastore never recorded.
+    pub async fn set_order_state(
+        &self,
+        client: &mut ClnRpc,
+        order_id: &str,
+        order_state: &str,
+    ) -> Result<(), Error> {
+        let mut order = self
+            .cache
+            .lock()
+            .await
+            .get(order_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("no such order {}", order_id))?;
+
+        order.result.order_state = order_state.to_string();
+        self.persist(client, &order).await?;
+
+        self.cache
+            .lock()
+            .await
+            .insert(order_id.to_string(), order);
+
+        Ok(())
+    }
+
+    async fn persist(&self, client: &mut ClnRpc, order: &StoredOrder) -> Result<(), Error> {
+        client
+            .call_typed(&DatastoreRequest {
+                key: vec![DATASTORE_PREFIX.to_string(), order.result.order_id.clone()],
+                string: Some(serde_json::to_string(order)?),
+                hex: None,
+                mode: Some(DatastoreMode::CREATE_OR_REPLACE),
+                generation: None,
+            })
+            .await?;
+
+        Ok(())
+    }
+}